@@ -6,19 +6,49 @@ use std::{
 };
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use futures::{ready, FutureExt};
+use futures::{ready, FutureExt, Sink, Stream};
 use quinn::{RecvStream, SendStream, VarInt};
-use tokio::io::AsyncRead;
-use tokio_util::codec::{Decoder, FramedRead};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::codec::{Decoder, Encoder, FramedRead};
 
-use super::proto::frame::{self, FrameHeader, HttpFrame, IntoPayload, PartialData};
+use super::proto::frame::{self, FrameHeader, HttpFrame, PartialData, ToPayload};
 use crate::{proto::ErrorCode, streams::Reset};
 
 pub type FrameStream = FramedRead<RecvStream, FrameDecoder>;
 
 impl Reset for FrameStream {
     fn reset(self, error_code: ErrorCode) {
-        let _ = self.into_inner().stop(error_code.0.into());
+        let _ = self.into_inner().stop(error_code.into());
+    }
+}
+
+/// Which kind of h3 stream a `FrameDecoder` is reading frames off of. Request/push streams and
+/// the control stream don't accept the same frame types, so the decoder needs to know which one
+/// it's attached to in order to reject frames that are illegal on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum StreamKind {
+    #[default]
+    Request,
+    Control,
+}
+
+impl StreamKind {
+    /// Whether `frame` is legal on this kind of stream, per
+    /// [RFC 9114 §7.2](https://www.rfc-editor.org/rfc/rfc9114#section-7.2). DATA is handled
+    /// separately in the `IncompleteData` branch of `FrameDecoder::decode`, since it never
+    /// reaches a fully-decoded `HttpFrame` for this check to see.
+    fn allows(self, frame: &HttpFrame) -> bool {
+        let control_only = matches!(
+            frame,
+            HttpFrame::CancelPush(_)
+                | HttpFrame::Settings(_)
+                | HttpFrame::Goaway(_)
+                | HttpFrame::MaxPushId(_)
+        );
+        match self {
+            StreamKind::Control => control_only,
+            StreamKind::Request => !control_only,
+        }
     }
 }
 
@@ -26,15 +56,55 @@ impl Reset for FrameStream {
 pub struct FrameDecoder {
     partial: Option<PartialData>,
     expected: Option<usize>,
+    max_frame_size: Option<usize>,
+    stream_kind: StreamKind,
+    settings_seen: bool,
 }
 
 impl FrameDecoder {
     pub fn stream<T: AsyncRead>(stream: T) -> FramedRead<T, Self> {
+        Self::build(stream, StreamKind::Request, None)
+    }
+
+    /// Like `stream`, but rejects any non-DATA frame whose advertised length exceeds
+    /// `max_frame_size` before its payload is buffered, so a peer can't force unbounded
+    /// allocation with a bogus HEADERS/SETTINGS length. DATA frames stay streamable past the
+    /// limit since their length only bounds the body, not a single in-memory buffer.
+    pub fn stream_with_max_size<T: AsyncRead>(
+        stream: T,
+        max_frame_size: usize,
+    ) -> FramedRead<T, Self> {
+        Self::build(stream, StreamKind::Request, Some(max_frame_size))
+    }
+
+    /// Like `stream`, but for the control stream, where a DATA frame (legal only on
+    /// request/push streams) is a protocol violation, and a second SETTINGS frame is too.
+    pub fn control_stream<T: AsyncRead>(stream: T) -> FramedRead<T, Self> {
+        Self::build(stream, StreamKind::Control, None)
+    }
+
+    /// Like `control_stream`, with the same `max_frame_size` cap as `stream_with_max_size`. The
+    /// control stream is where SETTINGS and GOAWAY land, so it's the one most worth capping.
+    pub fn control_stream_with_max_size<T: AsyncRead>(
+        stream: T,
+        max_frame_size: usize,
+    ) -> FramedRead<T, Self> {
+        Self::build(stream, StreamKind::Control, Some(max_frame_size))
+    }
+
+    fn build<T: AsyncRead>(
+        stream: T,
+        stream_kind: StreamKind,
+        max_frame_size: Option<usize>,
+    ) -> FramedRead<T, Self> {
         FramedRead::with_capacity(
             stream,
             FrameDecoder {
                 expected: None,
                 partial: None,
+                max_frame_size,
+                stream_kind,
+                settings_seen: false,
             },
             65535,
         )
@@ -75,16 +145,37 @@ impl Decoder for FrameDecoder {
             }
         }
 
-        let (pos, decoded) = decode!(src, |cur| HttpFrame::decode(cur));
+        // Check the declared length against the cap as soon as the header is fully buffered,
+        // rather than waiting on `Error::Incomplete` below, which only fires once the whole
+        // payload either has or hasn't arrived — by then a peer's oversized length has already
+        // been accepted if the rest of the (too-large) frame happened to be buffered already.
+        if let Some(max) = self.max_frame_size {
+            if let Some((frame_ty, len)) = frame::peek_header(src) {
+                if len > max {
+                    return Err(Error::FrameTooLarge {
+                        len,
+                        is_settings: frame_ty == frame::ty::SETTINGS,
+                    });
+                }
+            }
+        }
+
+        let (pos, decoded) = decode!(src, HttpFrame::decode);
 
         match decoded {
             Err(frame::Error::IncompleteData) => {
-                let (pos, decoded) = decode!(src, |cur| PartialData::decode(cur));
+                if self.stream_kind == StreamKind::Control {
+                    return Err(Error::UnexpectedFrame);
+                }
+
+                let (pos, decoded) = decode!(src, PartialData::decode);
                 let (partial, frame) = decoded?;
                 src.advance(pos);
                 self.expected = None;
-                self.partial = Some(partial);
-                if frame.len() > 0 {
+                if partial.remaining() > 0 {
+                    self.partial = Some(partial);
+                }
+                if !frame.is_empty() {
                     Ok(Some(HttpFrame::Data(frame)))
                 } else {
                     Ok(None)
@@ -96,6 +187,16 @@ impl Decoder for FrameDecoder {
             }
             Err(e) => Err(e.into()),
             Ok(frame) => {
+                if !self.stream_kind.allows(&frame) {
+                    return Err(Error::UnexpectedFrame);
+                }
+
+                if let HttpFrame::Settings(_) = frame {
+                    if self.settings_seen {
+                        return Err(Error::DuplicateSettings);
+                    }
+                    self.settings_seen = true;
+                }
                 src.advance(pos);
                 self.expected = None;
                 Ok(Some(frame))
@@ -104,21 +205,214 @@ impl Decoder for FrameDecoder {
     }
 }
 
-pub struct WriteFrame {
-    state: WriteFrameState,
+pub struct FrameEncoder {
+    backpressure_boundary: usize,
+}
+
+impl FrameEncoder {
+    const DEFAULT_BACKPRESSURE_BOUNDARY: usize = 65535;
+
+    pub fn new() -> Self {
+        Self {
+            backpressure_boundary: Self::DEFAULT_BACKPRESSURE_BOUNDARY,
+        }
+    }
+
+    pub fn backpressure_boundary(mut self, backpressure_boundary: usize) -> Self {
+        self.backpressure_boundary = backpressure_boundary;
+        self
+    }
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder<HttpFrame> for FrameEncoder {
+    type Error = io::Error;
+
+    fn encode(&mut self, frame: HttpFrame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        frame.encode_header(dst);
+        dst.put(frame.to_payload());
+        Ok(())
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The write half of a QUIC send stream, abstracted so the backpressure-buffering logic in
+/// `FramedWriter` and the header/payload state machine in `WriteFrame` can be driven by something
+/// other than a live `quinn::SendStream` in tests. Sealed: `WriteFrame`/`DataFrameWriter` need to
+/// name this trait in their public bounds, but it's only ever meant to be implemented by
+/// `SendStream` and the in-crate test mock.
+pub trait PollWrite: sealed::Sealed + Unpin {
+    fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<Result<usize, quinn::WriteError>>;
+    fn poll_finish(&mut self, cx: &mut Context) -> Poll<Result<(), quinn::WriteError>>;
+}
+
+impl sealed::Sealed for SendStream {}
+
+impl PollWrite for SendStream {
+    fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<Result<usize, quinn::WriteError>> {
+        self.write(buf).poll_unpin(cx)
+    }
+
+    fn poll_finish(&mut self, cx: &mut Context) -> Poll<Result<(), quinn::WriteError>> {
+        self.finish().poll_unpin(cx)
+    }
+}
+
+/// The buffering half of `Framed`: encodes frames into `write_buf` and only drains it to the
+/// sink once it crosses `backpressure_boundary`, so several small frames can be coalesced into
+/// fewer QUIC writes instead of flushing after every `Sink::start_send`. Split out from `Framed`
+/// so it can be driven by a mock `PollWrite` in tests instead of a live QUIC stream.
+struct FramedWriter<S = SendStream> {
+    send: S,
+    encoder: FrameEncoder,
+    write_buf: BytesMut,
+}
+
+impl<S: PollWrite> FramedWriter<S> {
+    fn new(send: S) -> Self {
+        Self {
+            send,
+            encoder: FrameEncoder::default(),
+            write_buf: BytesMut::new(),
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        if self.write_buf.len() < self.encoder.backpressure_boundary {
+            Poll::Ready(Ok(()))
+        } else {
+            self.poll_flush(cx)
+        }
+    }
+
+    fn start_send(&mut self, frame: HttpFrame) -> Result<(), Error> {
+        let mut write_buf = mem::take(&mut self.write_buf);
+        self.encoder.encode(frame, &mut write_buf)?;
+        self.write_buf = write_buf;
+        Ok(())
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        while !self.write_buf.is_empty() {
+            let wrote = ready!(self.send.poll_write(cx, &self.write_buf))?;
+            self.write_buf.advance(wrote);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        ready!(self.poll_flush(cx))?;
+        ready!(self.send.poll_finish(cx))?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Full-duplex frame transport over a bidirectional QUIC stream.
+///
+/// Reads are driven by a `FrameDecoder` exactly like `FrameStream`. Writes go through a
+/// `FrameEncoder` into an internal buffer that is only drained to the `SendStream` once it
+/// crosses `backpressure_boundary`, so several small frames can be coalesced into fewer QUIC
+/// writes instead of flushing after every `Sink::start_send`.
+pub struct Framed {
+    read: FramedRead<RecvStream, FrameDecoder>,
+    writer: FramedWriter,
+}
+
+impl Framed {
+    pub fn new(send: SendStream, recv: RecvStream) -> Self {
+        Self::build(send, recv, StreamKind::Request, None)
+    }
+
+    /// Like `new`, but rejects any non-DATA frame whose advertised length exceeds
+    /// `max_frame_size`, same as `FrameDecoder::stream_with_max_size`.
+    pub fn with_max_frame_size(send: SendStream, recv: RecvStream, max_frame_size: usize) -> Self {
+        Self::build(send, recv, StreamKind::Request, Some(max_frame_size))
+    }
+
+    /// Like `new`, but for the control stream, where a DATA frame and a second SETTINGS frame
+    /// are both protocol violations, same as `FrameDecoder::control_stream`.
+    pub fn control_stream(send: SendStream, recv: RecvStream) -> Self {
+        Self::build(send, recv, StreamKind::Control, None)
+    }
+
+    /// Like `control_stream`, with the same `max_frame_size` cap as `with_max_frame_size`.
+    pub fn control_stream_with_max_size(
+        send: SendStream,
+        recv: RecvStream,
+        max_frame_size: usize,
+    ) -> Self {
+        Self::build(send, recv, StreamKind::Control, Some(max_frame_size))
+    }
+
+    fn build(
+        send: SendStream,
+        recv: RecvStream,
+        stream_kind: StreamKind,
+        max_frame_size: Option<usize>,
+    ) -> Self {
+        Self {
+            read: FrameDecoder::build(recv, stream_kind, max_frame_size),
+            writer: FramedWriter::new(send),
+        }
+    }
+
+    pub fn with_backpressure_boundary(mut self, backpressure_boundary: usize) -> Self {
+        self.writer.encoder = self.writer.encoder.backpressure_boundary(backpressure_boundary);
+        self
+    }
+}
+
+impl Stream for Framed {
+    type Item = Result<HttpFrame, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.read).poll_next(cx)
+    }
+}
+
+impl Sink<HttpFrame> for Framed {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.writer.poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: HttpFrame) -> Result<(), Self::Error> {
+        self.writer.start_send(frame)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.writer.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.writer.poll_close(cx)
+    }
+}
+
+pub struct WriteFrame<S = SendStream> {
+    state: WriteFrameState<S>,
     payload: Option<Bytes>,
 }
 
-enum WriteFrameState {
-    Header(SendStream, [u8; VarInt::MAX_SIZE * 2], usize, usize),
-    Payload(SendStream, Bytes),
+enum WriteFrameState<S> {
+    Header(S, [u8; VarInt::MAX_SIZE * 2], usize, usize),
+    Payload(S, Bytes),
     Finished,
 }
 
-impl WriteFrame {
-    pub(crate) fn new<T>(send: SendStream, frame: T) -> Self
+impl<S: PollWrite> WriteFrame<S> {
+    pub(crate) fn new<T>(send: S, frame: T) -> Self
     where
-        T: FrameHeader + IntoPayload,
+        T: FrameHeader + ToPayload,
     {
         let mut buf = [0u8; VarInt::MAX_SIZE * 2];
         let remaining = {
@@ -128,28 +422,30 @@ impl WriteFrame {
         };
 
         Self {
-            payload: Some(frame.into_payload()),
+            payload: Some(frame.to_payload()),
             state: WriteFrameState::Header(send, buf, 0, buf.len() - remaining),
         }
     }
+}
 
+impl WriteFrame<SendStream> {
     pub fn reset(self, err_code: ErrorCode) {
         if let WriteFrameState::Header(mut s, ..) | WriteFrameState::Payload(mut s, _) = self.state
         {
-            s.reset(err_code.into());
+            let _ = s.reset(err_code.into());
         }
     }
 }
 
-impl Future for WriteFrame {
-    type Output = Result<SendStream, quinn::WriteError>;
+impl<S: PollWrite> Future for WriteFrame<S> {
+    type Output = Result<S, quinn::WriteError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
         loop {
             match self.state {
                 WriteFrameState::Finished => panic!("polled after finished"),
                 WriteFrameState::Header(ref mut send, ref h, ref mut start, len) => {
-                    let wrote = ready!(send.write(&h[*start..len]).poll_unpin(cx))?;
+                    let wrote = ready!(send.poll_write(cx, &h[*start..len]))?;
                     *start += wrote;
                     if *start < len {
                         continue;
@@ -162,7 +458,7 @@ impl Future for WriteFrame {
                     }
                 }
                 WriteFrameState::Payload(ref mut send, ref mut p) => {
-                    let wrote = ready!(send.write(p).poll_unpin(cx))?;
+                    let wrote = ready!(send.poll_write(cx, p))?;
                     p.advance(wrote);
                     if !p.is_empty() {
                         continue;
@@ -179,10 +475,148 @@ impl Future for WriteFrame {
     }
 }
 
+/// Streams a DATA frame body out of an `AsyncRead` source instead of requiring it fully
+/// materialized in memory up front. Reads into a reusable buffer until it fills up to
+/// `chunk_size`, the source hits EOF, or the source has no more bytes ready (with at least one
+/// byte already buffered), frames whatever was accumulated as a single DATA frame,
+/// and writes it out through the same `WriteFrame` header/payload machinery before reading the
+/// next chunk.
+pub struct DataFrameWriter<R, S = SendStream> {
+    state: DataFrameWriterState<R, S>,
+    buf: BytesMut,
+    chunk_size: usize,
+}
+
+enum DataFrameWriterState<R, S> {
+    Reading(S, R),
+    Writing(WriteFrame<S>, R),
+    Finished,
+}
+
+impl<R: AsyncRead + Unpin, S: PollWrite> DataFrameWriter<R, S> {
+    const DEFAULT_CHUNK_SIZE: usize = 65535;
+
+    pub fn new(send: S, reader: R) -> Self {
+        Self::with_chunk_size(send, reader, Self::DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(send: S, reader: R, chunk_size: usize) -> Self {
+        Self {
+            state: DataFrameWriterState::Reading(send, reader),
+            buf: BytesMut::with_capacity(chunk_size),
+            chunk_size,
+        }
+    }
+}
+
+impl<R> DataFrameWriter<R, SendStream> {
+    pub fn reset(self, err_code: ErrorCode) {
+        match self.state {
+            DataFrameWriterState::Reading(mut send, _) => {
+                let _ = send.reset(err_code.into());
+            }
+            DataFrameWriterState::Writing(write, _) => write.reset(err_code),
+            DataFrameWriterState::Finished => {}
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin, S: PollWrite> Future for DataFrameWriter<R, S> {
+    type Output = Result<S, DataFrameWriterError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match &mut this.state {
+                DataFrameWriterState::Finished => panic!("polled after finished"),
+                DataFrameWriterState::Reading(_, reader) => {
+                    this.buf.reserve(this.chunk_size);
+                    loop {
+                        if this.buf.len() >= this.chunk_size {
+                            break;
+                        }
+                        let mut limited = (&mut this.buf).limit(this.chunk_size - this.buf.len());
+                        let read = reader.read_buf(&mut limited);
+                        tokio::pin!(read);
+                        match read.poll(cx) {
+                            Poll::Pending => {
+                                if this.buf.is_empty() {
+                                    return Poll::Pending;
+                                }
+                                break;
+                            }
+                            Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                            Poll::Ready(Ok(0)) => break,
+                            Poll::Ready(Ok(_)) => {}
+                        }
+                    }
+
+                    if this.buf.is_empty() {
+                        let send =
+                            match mem::replace(&mut this.state, DataFrameWriterState::Finished) {
+                                DataFrameWriterState::Reading(send, _) => send,
+                                _ => unreachable!(),
+                            };
+                        return Poll::Ready(Ok(send));
+                    }
+
+                    let chunk = this.buf.split().freeze();
+                    this.state = match mem::replace(&mut this.state, DataFrameWriterState::Finished)
+                    {
+                        DataFrameWriterState::Reading(send, reader) => {
+                            let frame = frame::DataFrame { payload: chunk };
+                            DataFrameWriterState::Writing(WriteFrame::new(send, frame), reader)
+                        }
+                        _ => unreachable!(),
+                    };
+                }
+                DataFrameWriterState::Writing(write, _) => {
+                    let send = ready!(Pin::new(write).poll(cx))?;
+                    this.state = match mem::replace(&mut this.state, DataFrameWriterState::Finished)
+                    {
+                        DataFrameWriterState::Writing(_, reader) => {
+                            DataFrameWriterState::Reading(send, reader)
+                        }
+                        _ => unreachable!(),
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DataFrameWriterError {
+    Read(io::Error),
+    Write(quinn::WriteError),
+}
+
+impl From<io::Error> for DataFrameWriterError {
+    fn from(err: io::Error) -> Self {
+        DataFrameWriterError::Read(err)
+    }
+}
+
+impl From<quinn::WriteError> for DataFrameWriterError {
+    fn from(err: quinn::WriteError) -> Self {
+        DataFrameWriterError::Write(err)
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Proto(frame::Error),
     Io(io::Error),
+    /// A frame's advertised length exceeded the decoder's configured `max_frame_size`.
+    FrameTooLarge {
+        len: usize,
+        /// SETTINGS frames use a distinct error code from oversized frames in general.
+        is_settings: bool,
+    },
+    /// A frame was decoded on a stream it isn't legal on, e.g. DATA on the control stream.
+    UnexpectedFrame,
+    /// A second SETTINGS frame arrived on the control stream.
+    DuplicateSettings,
 }
 
 impl Error {
@@ -192,6 +626,15 @@ impl Error {
             Error::Proto(frame::Error::Settings(_)) => ErrorCode::SETTINGS_ERROR,
             Error::Proto(frame::Error::UnsupportedFrame(_)) => ErrorCode::FRAME_UNEXPECTED,
             Error::Proto(_) => ErrorCode::FRAME_ERROR,
+            Error::FrameTooLarge { is_settings, .. } => {
+                if *is_settings {
+                    ErrorCode::SETTINGS_ERROR
+                } else {
+                    ErrorCode::FRAME_ERROR
+                }
+            }
+            Error::UnexpectedFrame => ErrorCode::FRAME_UNEXPECTED,
+            Error::DuplicateSettings => ErrorCode::SETTINGS_ERROR,
         }
     }
 }
@@ -208,11 +651,22 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<quinn::WriteError> for Error {
+    fn from(err: quinn::WriteError) -> Self {
+        Error::Io(io::Error::other(err))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::proto::frame;
 
+    fn encode_frame<T: FrameHeader + ToPayload>(frame: &T, buf: &mut BytesMut) {
+        frame.encode_header(buf);
+        buf.put(frame.to_payload());
+    }
+
     #[test]
     fn one_frame() {
         let frame = frame::HeadersFrame {
@@ -220,7 +674,7 @@ mod tests {
         };
 
         let mut buf = BytesMut::with_capacity(16);
-        frame.encode(&mut buf);
+        encode_frame(&frame, &mut buf);
 
         let mut decoder = FrameDecoder::default();
         assert_matches!(decoder.decode(&mut buf), Ok(Some(HttpFrame::Headers(_))));
@@ -233,7 +687,7 @@ mod tests {
         };
 
         let mut buf = BytesMut::with_capacity(16);
-        frame.encode(&mut buf);
+        encode_frame(&frame, &mut buf);
         buf.truncate(buf.len() - 1);
 
         let mut decoder = FrameDecoder::default();
@@ -256,7 +710,7 @@ mod tests {
 
         let mut buf = BytesMut::with_capacity(64);
         for frame in frames.iter() {
-            frame.encode(&mut buf);
+            encode_frame(frame, &mut buf);
         }
         buf.truncate(buf.len() - 1);
 
@@ -265,4 +719,199 @@ mod tests {
         assert_matches!(decoder.decode(&mut buf), Ok(Some(HttpFrame::Data(_))));
         assert_matches!(decoder.decode(&mut buf), Ok(None));
     }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let frame = frame::HeadersFrame {
+            encoded: vec![0u8; 128].into(),
+        };
+
+        let mut buf = BytesMut::with_capacity(256);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder {
+            max_frame_size: Some(16),
+            ..FrameDecoder::default()
+        };
+        assert_matches!(decoder.decode(&mut buf), Err(Error::FrameTooLarge { .. }));
+    }
+
+    #[test]
+    fn oversized_data_frame_still_streams() {
+        let frame = frame::DataFrame {
+            payload: vec![0u8; 128].into(),
+        };
+
+        let mut buf = BytesMut::with_capacity(256);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder {
+            max_frame_size: Some(16),
+            ..FrameDecoder::default()
+        };
+        assert_matches!(decoder.decode(&mut buf), Ok(Some(HttpFrame::Data(_))));
+    }
+
+    #[test]
+    fn data_frame_rejected_on_control_stream() {
+        let frame = frame::DataFrame {
+            payload: b"body"[..].into(),
+        };
+
+        let mut buf = BytesMut::with_capacity(16);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder {
+            stream_kind: StreamKind::Control,
+            ..FrameDecoder::default()
+        };
+        assert_matches!(decoder.decode(&mut buf), Err(Error::UnexpectedFrame));
+    }
+
+    #[test]
+    fn headers_rejected_on_control_stream() {
+        let frame = frame::HeadersFrame {
+            encoded: b"header"[..].into(),
+        };
+
+        let mut buf = BytesMut::with_capacity(16);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder {
+            stream_kind: StreamKind::Control,
+            ..FrameDecoder::default()
+        };
+        assert_matches!(decoder.decode(&mut buf), Err(Error::UnexpectedFrame));
+    }
+
+    #[test]
+    fn settings_rejected_on_request_stream() {
+        let frame = frame::SettingsFrame::default();
+
+        let mut buf = BytesMut::with_capacity(16);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder::default();
+        assert_matches!(decoder.decode(&mut buf), Err(Error::UnexpectedFrame));
+    }
+
+    #[test]
+    fn duplicate_settings_rejected_on_control_stream() {
+        let frame = frame::SettingsFrame::default();
+
+        let mut buf = BytesMut::with_capacity(32);
+        encode_frame(&frame, &mut buf);
+        encode_frame(&frame, &mut buf);
+
+        let mut decoder = FrameDecoder {
+            stream_kind: StreamKind::Control,
+            ..FrameDecoder::default()
+        };
+        assert_matches!(decoder.decode(&mut buf), Ok(Some(HttpFrame::Settings(_))));
+        assert_matches!(decoder.decode(&mut buf), Err(Error::DuplicateSettings));
+    }
+
+    impl sealed::Sealed for Vec<u8> {}
+
+    impl PollWrite for Vec<u8> {
+        fn poll_write(
+            &mut self,
+            _cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, quinn::WriteError>> {
+            self.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_finish(&mut self, _cx: &mut Context) -> Poll<Result<(), quinn::WriteError>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn noop_context() -> Context<'static> {
+        Context::from_waker(futures::task::noop_waker_ref())
+    }
+
+    #[test]
+    fn framed_writer_buffers_below_boundary_and_flushes_once_crossed() {
+        let mut writer = FramedWriter {
+            send: Vec::new(),
+            encoder: FrameEncoder::default().backpressure_boundary(8),
+            write_buf: BytesMut::new(),
+        };
+        let mut cx = noop_context();
+
+        let small = HttpFrame::Headers(frame::HeadersFrame {
+            encoded: b"a"[..].into(),
+        });
+
+        writer.start_send(small.clone()).unwrap();
+        assert!(writer.write_buf.len() < 8);
+        assert_matches!(writer.poll_ready(&mut cx), Poll::Ready(Ok(())));
+        assert!(
+            writer.send.is_empty(),
+            "below the boundary, nothing should be flushed yet"
+        );
+
+        for _ in 0..10 {
+            writer.start_send(small.clone()).unwrap();
+        }
+        assert!(writer.write_buf.len() >= 8);
+        assert_matches!(writer.poll_ready(&mut cx), Poll::Ready(Ok(())));
+        assert!(
+            !writer.send.is_empty() && writer.write_buf.is_empty(),
+            "crossing the boundary should flush the buffer to the sink"
+        );
+    }
+
+    struct SmallReads<'a> {
+        data: &'a [u8],
+        max_read: usize,
+    }
+
+    impl<'a> AsyncRead for SmallReads<'a> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context,
+            buf: &mut tokio::io::ReadBuf,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            let n = this.max_read.min(this.data.len()).min(buf.remaining());
+            buf.put_slice(&this.data[..n]);
+            this.data = &this.data[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn data_frame_writer_coalesces_small_reads_up_to_chunk_size() {
+        let input: Vec<u8> = (0u8..50).collect();
+        let reader = SmallReads {
+            data: &input,
+            max_read: 3,
+        };
+
+        let mut writer = DataFrameWriter::with_chunk_size(Vec::new(), reader, 16);
+        let mut cx = noop_context();
+        let written = match Pin::new(&mut writer).poll(&mut cx) {
+            Poll::Ready(Ok(send)) => send,
+            other => panic!("expected the writer to finish synchronously, got {:?}", other),
+        };
+
+        let mut buf = BytesMut::from(&written[..]);
+        let mut decoder = FrameDecoder::default();
+        let mut payload = Vec::new();
+        let mut frame_count = 0;
+        while let Ok(Some(HttpFrame::Data(data))) = decoder.decode(&mut buf) {
+            payload.extend_from_slice(&data.payload);
+            frame_count += 1;
+        }
+
+        assert_eq!(payload, input);
+        assert!(
+            frame_count < input.len() / 3,
+            "expected reads to coalesce up to chunk_size instead of one frame per small read, got {} frames",
+            frame_count
+        );
+    }
 }
@@ -0,0 +1,7 @@
+#[cfg(test)]
+#[macro_use]
+extern crate matches;
+
+pub mod frame;
+pub mod proto;
+pub mod streams;
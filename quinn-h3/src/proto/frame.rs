@@ -0,0 +1,523 @@
+use std::io;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Frame type codes, as assigned by [RFC 9114 §11.2.1](https://www.rfc-editor.org/rfc/rfc9114#section-11.2.1).
+pub(crate) mod ty {
+    pub const DATA: u64 = 0x0;
+    pub const HEADERS: u64 = 0x1;
+    pub const CANCEL_PUSH: u64 = 0x3;
+    pub const SETTINGS: u64 = 0x4;
+    pub const PUSH_PROMISE: u64 = 0x5;
+    pub const GOAWAY: u64 = 0x7;
+    pub const MAX_PUSH_ID: u64 = 0xd;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpFrame {
+    Data(DataFrame),
+    Headers(HeadersFrame),
+    CancelPush(CancelPushFrame),
+    Settings(SettingsFrame),
+    PushPromise(PushPromiseFrame),
+    Goaway(GoawayFrame),
+    MaxPushId(MaxPushIdFrame),
+}
+
+impl HttpFrame {
+    pub(crate) fn decode<B: Buf>(buf: &mut B) -> Result<Self, Error> {
+        let start = buf.remaining();
+
+        let frame_ty = read_varint(buf, start)?;
+        if frame_ty == ty::DATA {
+            return Err(Error::IncompleteData);
+        }
+
+        let len = read_varint(buf, start)? as usize;
+        let consumed = start - buf.remaining();
+        if buf.remaining() < len {
+            return Err(Error::Incomplete(consumed + len));
+        }
+
+        let mut payload = buf.copy_to_bytes(len);
+        match frame_ty {
+            ty::HEADERS => Ok(HttpFrame::Headers(HeadersFrame { encoded: payload })),
+            ty::CANCEL_PUSH => Ok(HttpFrame::CancelPush(CancelPushFrame {
+                push_id: read_varint(&mut payload, len)?,
+            })),
+            ty::SETTINGS => Ok(HttpFrame::Settings(SettingsFrame::decode(payload)?)),
+            ty::PUSH_PROMISE => {
+                let push_id = read_varint(&mut payload, len)?;
+                Ok(HttpFrame::PushPromise(PushPromiseFrame {
+                    push_id,
+                    encoded: payload,
+                }))
+            }
+            ty::GOAWAY => Ok(HttpFrame::Goaway(GoawayFrame {
+                stream_id: read_varint(&mut payload, len)?,
+            })),
+            ty::MAX_PUSH_ID => Ok(HttpFrame::MaxPushId(MaxPushIdFrame {
+                push_id: read_varint(&mut payload, len)?,
+            })),
+            _ => Err(Error::UnsupportedFrame(frame_ty)),
+        }
+    }
+
+}
+
+/// Peeks the frame type and declared payload length off the front of `src` without consuming
+/// anything, so a caller can enforce a size policy (like a max frame size) as soon as the header
+/// is fully buffered, rather than waiting for the whole payload to arrive. Returns `None` if the
+/// header itself isn't fully buffered yet, or if the frame is DATA, which streams past any cap.
+pub(crate) fn peek_header(src: &BytesMut) -> Option<(u64, usize)> {
+    let mut cur = io::Cursor::new(src);
+    let frame_ty = decode_varint(&mut cur)?;
+    if frame_ty == ty::DATA {
+        return None;
+    }
+    let len = decode_varint(&mut cur)? as usize;
+    Some((frame_ty, len))
+}
+
+pub trait FrameHeader {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B);
+}
+
+pub trait ToPayload {
+    fn to_payload(&self) -> Bytes;
+}
+
+impl FrameHeader for HttpFrame {
+    fn len(&self) -> usize {
+        match self {
+            HttpFrame::Data(f) => f.len(),
+            HttpFrame::Headers(f) => f.len(),
+            HttpFrame::CancelPush(f) => f.len(),
+            HttpFrame::Settings(f) => f.len(),
+            HttpFrame::PushPromise(f) => f.len(),
+            HttpFrame::Goaway(f) => f.len(),
+            HttpFrame::MaxPushId(f) => f.len(),
+        }
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        match self {
+            HttpFrame::Data(f) => f.encode_header(buf),
+            HttpFrame::Headers(f) => f.encode_header(buf),
+            HttpFrame::CancelPush(f) => f.encode_header(buf),
+            HttpFrame::Settings(f) => f.encode_header(buf),
+            HttpFrame::PushPromise(f) => f.encode_header(buf),
+            HttpFrame::Goaway(f) => f.encode_header(buf),
+            HttpFrame::MaxPushId(f) => f.encode_header(buf),
+        }
+    }
+}
+
+impl ToPayload for HttpFrame {
+    fn to_payload(&self) -> Bytes {
+        match self {
+            HttpFrame::Data(f) => f.to_payload(),
+            HttpFrame::Headers(f) => f.to_payload(),
+            HttpFrame::CancelPush(f) => f.to_payload(),
+            HttpFrame::Settings(f) => f.to_payload(),
+            HttpFrame::PushPromise(f) => f.to_payload(),
+            HttpFrame::Goaway(f) => f.to_payload(),
+            HttpFrame::MaxPushId(f) => f.to_payload(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFrame {
+    pub payload: Bytes,
+}
+
+impl DataFrame {
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+}
+
+impl FrameHeader for DataFrame {
+    fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::DATA, buf);
+        encode_varint(self.payload.len() as u64, buf);
+    }
+}
+
+impl ToPayload for DataFrame {
+    fn to_payload(&self) -> Bytes {
+        self.payload.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadersFrame {
+    pub encoded: Bytes,
+}
+
+impl FrameHeader for HeadersFrame {
+    fn len(&self) -> usize {
+        self.encoded.len()
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::HEADERS, buf);
+        encode_varint(self.encoded.len() as u64, buf);
+    }
+}
+
+impl ToPayload for HeadersFrame {
+    fn to_payload(&self) -> Bytes {
+        self.encoded.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelPushFrame {
+    pub push_id: u64,
+}
+
+impl FrameHeader for CancelPushFrame {
+    fn len(&self) -> usize {
+        varint_len(self.push_id)
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::CANCEL_PUSH, buf);
+        encode_varint(self.len() as u64, buf);
+    }
+}
+
+impl ToPayload for CancelPushFrame {
+    fn to_payload(&self) -> Bytes {
+        let mut payload = Vec::with_capacity(self.len());
+        encode_varint(self.push_id, &mut payload);
+        payload.into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoawayFrame {
+    pub stream_id: u64,
+}
+
+impl FrameHeader for GoawayFrame {
+    fn len(&self) -> usize {
+        varint_len(self.stream_id)
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::GOAWAY, buf);
+        encode_varint(self.len() as u64, buf);
+    }
+}
+
+impl ToPayload for GoawayFrame {
+    fn to_payload(&self) -> Bytes {
+        let mut payload = Vec::with_capacity(self.len());
+        encode_varint(self.stream_id, &mut payload);
+        payload.into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxPushIdFrame {
+    pub push_id: u64,
+}
+
+impl FrameHeader for MaxPushIdFrame {
+    fn len(&self) -> usize {
+        varint_len(self.push_id)
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::MAX_PUSH_ID, buf);
+        encode_varint(self.len() as u64, buf);
+    }
+}
+
+impl ToPayload for MaxPushIdFrame {
+    fn to_payload(&self) -> Bytes {
+        let mut payload = Vec::with_capacity(self.len());
+        encode_varint(self.push_id, &mut payload);
+        payload.into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushPromiseFrame {
+    pub push_id: u64,
+    pub encoded: Bytes,
+}
+
+impl FrameHeader for PushPromiseFrame {
+    fn len(&self) -> usize {
+        varint_len(self.push_id) + self.encoded.len()
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::PUSH_PROMISE, buf);
+        encode_varint(self.len() as u64, buf);
+    }
+}
+
+impl ToPayload for PushPromiseFrame {
+    fn to_payload(&self) -> Bytes {
+        let mut payload = Vec::with_capacity(self.len());
+        encode_varint(self.push_id, &mut payload);
+        payload.extend_from_slice(&self.encoded);
+        payload.into()
+    }
+}
+
+/// Well-known SETTINGS identifiers, per
+/// [RFC 9114 §7.2.4.1](https://www.rfc-editor.org/rfc/rfc9114#section-7.2.4.1) and the QPACK RFC.
+pub const SETTINGS_QPACK_MAX_TABLE_CAPACITY: u64 = 0x1;
+pub const SETTINGS_MAX_FIELD_SECTION_SIZE: u64 = 0x6;
+pub const SETTINGS_QPACK_BLOCKED_STREAMS: u64 = 0x7;
+
+/// A typed SETTINGS identifier -> value map, preserving the order entries were seen in.
+///
+/// Reserved "grease" identifiers (`0x1f * N + 0x21`, per
+/// [RFC 9114 §7.2.4.1](https://www.rfc-editor.org/rfc/rfc9114#section-7.2.4.1)) are ignored on
+/// decode rather than stored, since peers are required to send and ignore them but we have
+/// nothing to do with their values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SettingsFrame {
+    entries: Vec<(u64, u64)>,
+}
+
+impl SettingsFrame {
+    pub fn get(&self, identifier: u64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|(id, _)| *id == identifier)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn insert(&mut self, identifier: u64, value: u64) {
+        self.entries.push((identifier, value));
+    }
+
+    fn decode(mut payload: Bytes) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        while payload.has_remaining() {
+            let remaining = payload.remaining();
+            let identifier = read_varint(&mut payload, remaining)?;
+            let value = read_varint(&mut payload, remaining)?;
+
+            if is_grease(identifier) {
+                continue;
+            }
+
+            if entries.iter().any(|(id, _)| *id == identifier) {
+                return Err(Error::Settings(SettingsError::Repeated(identifier)));
+            }
+
+            entries.push((identifier, value));
+        }
+        Ok(SettingsFrame { entries })
+    }
+}
+
+fn is_grease(identifier: u64) -> bool {
+    identifier >= 0x21 && (identifier - 0x21).is_multiple_of(0x1f)
+}
+
+impl FrameHeader for SettingsFrame {
+    fn len(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(id, value)| varint_len(*id) + varint_len(*value))
+            .sum()
+    }
+
+    fn encode_header<B: BufMut>(&self, buf: &mut B) {
+        encode_varint(ty::SETTINGS, buf);
+        encode_varint(self.len() as u64, buf);
+    }
+}
+
+impl ToPayload for SettingsFrame {
+    fn to_payload(&self) -> Bytes {
+        let mut payload = Vec::with_capacity(self.len());
+        for (id, value) in &self.entries {
+            encode_varint(*id, &mut payload);
+            encode_varint(*value, &mut payload);
+        }
+        payload.into()
+    }
+}
+
+/// Decodes a QUIC variable-length integer, returning `None` if not enough bytes are buffered.
+fn decode_varint<B: Buf>(buf: &mut B) -> Option<u64> {
+    let chunk = buf.chunk();
+    let first = *chunk.first()?;
+    let len = 1usize << (first >> 6);
+    if buf.remaining() < len {
+        return None;
+    }
+
+    let mut value = u64::from(first & 0x3f);
+    for &byte in &chunk[1..len] {
+        value = (value << 8) | u64::from(byte);
+    }
+    buf.advance(len);
+    Some(value)
+}
+
+fn read_varint<B: Buf>(buf: &mut B, start_remaining: usize) -> Result<u64, Error> {
+    decode_varint(buf).ok_or(Error::Incomplete(start_remaining + 1))
+}
+
+fn encode_varint<B: BufMut>(value: u64, buf: &mut B) {
+    if value < 64 {
+        buf.put_u8(value as u8);
+    } else if value < 16_384 {
+        buf.put_u16(0b01 << 14 | value as u16);
+    } else if value < 1_073_741_824 {
+        buf.put_u32(0b10 << 30 | value as u32);
+    } else {
+        buf.put_u64(0b11 << 62 | value);
+    }
+}
+
+fn varint_len(value: u64) -> usize {
+    if value < 64 {
+        1
+    } else if value < 16_384 {
+        2
+    } else if value < 1_073_741_824 {
+        4
+    } else {
+        8
+    }
+}
+
+/// A DATA frame whose payload has only been partially received.
+///
+/// `FrameDecoder` streams DATA payloads out as they arrive instead of buffering a whole frame,
+/// so the header is decoded once and the remaining byte count is tracked here across calls.
+pub struct PartialData {
+    remaining: usize,
+}
+
+impl PartialData {
+    pub(crate) fn decode<B: Buf>(buf: &mut B) -> Result<(PartialData, DataFrame), Error> {
+        let start = buf.remaining();
+        let frame_ty = read_varint(buf, start)?;
+        debug_assert_eq!(frame_ty, ty::DATA, "PartialData::decode called on a non-DATA frame");
+        let len = read_varint(buf, start)? as usize;
+
+        let available = buf.remaining().min(len);
+        let payload = buf.copy_to_bytes(available);
+
+        Ok((
+            PartialData {
+                remaining: len - available,
+            },
+            DataFrame { payload },
+        ))
+    }
+
+    pub(crate) fn decode_data<B: Buf>(&mut self, buf: &mut B) -> DataFrame {
+        let available = buf.remaining().min(self.remaining);
+        let payload = buf.copy_to_bytes(available);
+        self.remaining -= available;
+        DataFrame { payload }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The frame has too few bytes buffered to be decoded yet; retry once at least this many
+    /// bytes are available.
+    Incomplete(usize),
+    /// A DATA frame's type was read (its length is not read yet); the caller should decode it
+    /// from the start via `PartialData`.
+    IncompleteData,
+    /// A decoded SETTINGS frame violated the SETTINGS-specific rules (e.g. a repeated
+    /// identifier).
+    Settings(SettingsError),
+    /// The frame type isn't one this implementation knows how to decode.
+    UnsupportedFrame(u64),
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Repeated(u64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(frame: HttpFrame) {
+        let mut buf = BytesMut::with_capacity(64);
+        frame.encode_header(&mut buf);
+        buf.put(frame.to_payload());
+
+        let mut cur = io::Cursor::new(&buf);
+        assert_eq!(HttpFrame::decode(&mut cur).unwrap(), frame);
+    }
+
+    #[test]
+    fn cancel_push_round_trips() {
+        round_trip(HttpFrame::CancelPush(CancelPushFrame { push_id: 42 }));
+    }
+
+    #[test]
+    fn goaway_round_trips() {
+        round_trip(HttpFrame::Goaway(GoawayFrame { stream_id: 1337 }));
+    }
+
+    #[test]
+    fn max_push_id_round_trips() {
+        round_trip(HttpFrame::MaxPushId(MaxPushIdFrame { push_id: 9000 }));
+    }
+
+    #[test]
+    fn push_promise_round_trips() {
+        round_trip(HttpFrame::PushPromise(PushPromiseFrame {
+            push_id: 7,
+            encoded: b"encoded header block"[..].into(),
+        }));
+    }
+
+    #[test]
+    fn push_promise_with_large_push_id_round_trips() {
+        // A push_id needing a multi-byte varint shifts where `encoded` starts within the shared
+        // payload buffer; an off-by-one in that split would corrupt the header block.
+        round_trip(HttpFrame::PushPromise(PushPromiseFrame {
+            push_id: 1_073_741_824,
+            encoded: b"encoded header block"[..].into(),
+        }));
+    }
+
+    #[test]
+    fn settings_round_trips() {
+        let mut frame = SettingsFrame::default();
+        frame.insert(SETTINGS_QPACK_MAX_TABLE_CAPACITY, 100);
+        frame.insert(SETTINGS_QPACK_BLOCKED_STREAMS, 16);
+        round_trip(HttpFrame::Settings(frame));
+    }
+}
@@ -0,0 +1,29 @@
+pub mod frame;
+
+/// An HTTP/3 error code, as carried on `STOP_SENDING`/`RESET_STREAM` and `CONNECTION_CLOSE`.
+///
+/// See [RFC 9114 §8.1](https://www.rfc-editor.org/rfc/rfc9114#section-8.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode(pub(crate) u64);
+
+impl ErrorCode {
+    pub const NO_ERROR: ErrorCode = ErrorCode(0x100);
+    pub const GENERAL_PROTOCOL_ERROR: ErrorCode = ErrorCode(0x101);
+    pub const INTERNAL_ERROR: ErrorCode = ErrorCode(0x102);
+    pub const STREAM_CREATION_ERROR: ErrorCode = ErrorCode(0x103);
+    pub const CLOSED_CRITICAL_STREAM: ErrorCode = ErrorCode(0x104);
+    pub const FRAME_UNEXPECTED: ErrorCode = ErrorCode(0x105);
+    pub const FRAME_ERROR: ErrorCode = ErrorCode(0x106);
+    pub const EXCESSIVE_LOAD: ErrorCode = ErrorCode(0x107);
+    pub const ID_ERROR: ErrorCode = ErrorCode(0x108);
+    pub const SETTINGS_ERROR: ErrorCode = ErrorCode(0x109);
+    pub const MISSING_SETTINGS: ErrorCode = ErrorCode(0x10a);
+    pub const REQUEST_REJECTED: ErrorCode = ErrorCode(0x10b);
+    pub const REQUEST_CANCELLED: ErrorCode = ErrorCode(0x10c);
+}
+
+impl From<ErrorCode> for quinn::VarInt {
+    fn from(code: ErrorCode) -> Self {
+        quinn::VarInt::from_u64(code.0).expect("error codes fit in a VarInt")
+    }
+}
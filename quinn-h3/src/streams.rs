@@ -0,0 +1,7 @@
+use crate::proto::ErrorCode;
+
+/// A stream-like type that can be abruptly torn down with an h3 error code instead of being
+/// driven to a clean finish.
+pub trait Reset {
+    fn reset(self, error_code: ErrorCode);
+}